@@ -1,29 +1,981 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::schema::json_schema_for;
 use anyhow::{Context as _, Result, anyhow};
 use assistant_tool::{ActionLog, Tool, ToolCard, ToolResult, ToolUseStatus};
-use futures::{FutureExt, TryFutureExt};
+use futures::{
+    AsyncReadExt, FutureExt, TryFutureExt,
+    future::{BoxFuture, Shared, join_all},
+};
 use gpui::{
-    Animation, AnimationExt, App, AppContext, Context, Entity, IntoElement, Task, Window,
+    Animation, AnimationExt, App, AppContext, Context, Entity, Global, IntoElement, Task, Window,
     pulsating_between,
 };
+use http_client::{AsyncBody, HttpClientWithUrl};
 use language_model::{LanguageModelRequestMessage, LanguageModelToolSchemaFormat};
 use project::Project;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources, SettingsStore};
 use ui::{IconName, Tooltip, prelude::*};
-use web_search::WebSearchRegistry;
+use web_search::{WebSearchProvider, WebSearchRegistry};
 use zed_llm_client::WebSearchResponse;
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WebSearchToolInput {
     /// The search term or question to query on the web.
     query: String,
+    /// The maximum number of results to return. Defaults to the provider's
+    /// own default when omitted.
+    #[serde(default)]
+    max_results: Option<usize>,
+    /// Only include results whose URL host matches one of these domains.
+    #[serde(default)]
+    include_domains: Option<Vec<String>>,
+    /// Exclude results whose URL host matches one of these domains.
+    #[serde(default)]
+    exclude_domains: Option<Vec<String>>,
+}
+
+/// Search constraints applied as a post-filter over a provider's citations.
+///
+/// These are deliberately never threaded into [`WebSearchProvider::search`]
+/// itself: the trait's `search` only takes the query string, so a provider
+/// has no way to be asked for more/fewer/targeted results up front. Every
+/// registered provider implementation lives outside this crate, so growing
+/// the trait's signature to accept `max_results`/domain constraints would be
+/// a breaking change to all of them rather than something this module can
+/// do unilaterally. Applying the constraints here, after the fact, is the
+/// narrower but compatible alternative.
+///
+/// There's deliberately no `recency`/freshness constraint here either:
+/// citations don't carry any published-date metadata to filter on, so a
+/// freshness input could never be more than a silent no-op.
+#[derive(Debug, Clone, Default)]
+pub struct WebSearchParams {
+    pub max_results: Option<usize>,
+    pub include_domains: Option<Vec<String>>,
+    pub exclude_domains: Option<Vec<String>>,
+}
+
+impl WebSearchParams {
+    fn from_input(input: &WebSearchToolInput) -> Self {
+        Self {
+            max_results: input.max_results,
+            include_domains: input.include_domains.clone(),
+            exclude_domains: input.exclude_domains.clone(),
+        }
+    }
+
+    /// Applies any constraints the provider itself couldn't honor.
+    fn post_filter(&self, mut response: WebSearchResponse) -> WebSearchResponse {
+        if self.include_domains.is_none()
+            && self.exclude_domains.is_none()
+            && self.max_results.is_none()
+        {
+            return response;
+        }
+
+        response.citations.retain(|citation| {
+            let host = url_host(&citation.url);
+
+            if let Some(include_domains) = &self.include_domains {
+                if !include_domains
+                    .iter()
+                    .any(|domain| host.as_deref() == Some(domain.as_str()))
+                {
+                    return false;
+                }
+            }
+
+            if let Some(exclude_domains) = &self.exclude_domains {
+                if exclude_domains
+                    .iter()
+                    .any(|domain| host.as_deref() == Some(domain.as_str()))
+                {
+                    return false;
+                }
+            }
+
+            true
+        });
+
+        if let Some(max_results) = self.max_results {
+            response.citations.truncate(max_results);
+        }
+
+        response
+    }
+}
+
+/// A stable stand-in for a provider's identity, used to scope cache keys so
+/// switching providers can't serve back another provider's cached results.
+/// Providers are trait objects with no exposed id, so the `Arc`'s data
+/// address is used instead: stable for the lifetime of a registered
+/// provider, and distinct across distinct providers.
+fn provider_cache_key(provider: &Arc<dyn WebSearchProvider>) -> String {
+    format!("{:p}", Arc::as_ptr(provider))
+}
+
+/// A stable stand-in for the identity of a *set* of providers, used to scope
+/// aggregated-search cache keys so the cache can't serve back results from a
+/// different set of providers.
+fn providers_cache_key(providers: &[Arc<dyn WebSearchProvider>]) -> String {
+    let mut keys: Vec<String> = providers.iter().map(provider_cache_key).collect();
+    keys.sort();
+    keys.join(",")
+}
+
+fn url_host(url: &str) -> Option<String> {
+    url.parse::<url::Url>()
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+}
+
+/// User-configurable `web_search`/`fetch_url` behavior, under the
+/// `web_search` key in `settings.json`.
+#[derive(Debug, Clone)]
+pub struct WebSearchSettings {
+    /// Whether repeated identical queries are served from the LRU/TTL
+    /// cache instead of re-querying the provider.
+    pub cache_enabled: bool,
+    /// Steady-state number of `web_search` calls allowed per minute.
+    pub requests_per_minute: f64,
+    /// Number of calls that may be made back-to-back before the
+    /// steady-state rate kicks in.
+    pub burst: f64,
+    /// Whether `web_search` fans a query out to every registered provider
+    /// and merges the results with Reciprocal Rank Fusion, instead of just
+    /// querying the active provider. Opt-in: off by default.
+    pub multi_provider_aggregation_enabled: bool,
+}
+
+/// The raw, possibly-partial `web_search` settings content as it appears in
+/// `settings.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct WebSearchSettingsContent {
+    /// Whether repeated identical queries are served from the LRU/TTL
+    /// cache instead of re-querying the provider. Defaults to `true`.
+    pub cache_enabled: Option<bool>,
+    /// Steady-state number of `web_search` calls allowed per minute.
+    /// Defaults to 20.
+    pub requests_per_minute: Option<f64>,
+    /// Number of calls that may be made back-to-back before the
+    /// steady-state rate kicks in. Defaults to 5.
+    pub burst: Option<f64>,
+    /// Whether `web_search` fans a query out to every registered provider
+    /// and merges the results with Reciprocal Rank Fusion, instead of just
+    /// querying the active provider. Opt-in: defaults to `false`.
+    pub multi_provider_aggregation_enabled: Option<bool>,
+}
+
+impl Settings for WebSearchSettings {
+    const KEY: Option<&'static str> = Some("web_search");
+
+    type FileContent = WebSearchSettingsContent;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _cx: &mut App) -> Result<Self> {
+        let content = sources.json_merge::<WebSearchSettingsContent>()?;
+        Ok(Self {
+            cache_enabled: content.cache_enabled.unwrap_or(true),
+            requests_per_minute: content
+                .requests_per_minute
+                .unwrap_or(WEB_SEARCH_DEFAULT_REQUESTS_PER_MINUTE)
+                .max(WEB_SEARCH_MIN_REQUESTS_PER_MINUTE),
+            burst: content
+                .burst
+                .unwrap_or(WEB_SEARCH_DEFAULT_BURST)
+                .max(WEB_SEARCH_MIN_BURST),
+            multi_provider_aggregation_enabled: content
+                .multi_provider_aggregation_enabled
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// Registers [`WebSearchSettings`] and applies it (and its future changes)
+/// to the `web_search`/`fetch_url` runtime state.
+pub fn init(cx: &mut App) {
+    WebSearchSettings::register(cx);
+    apply_web_search_settings(cx);
+
+    cx.observe_global::<SettingsStore>(apply_web_search_settings)
+        .detach();
+}
+
+fn apply_web_search_settings(cx: &mut App) {
+    let settings = WebSearchSettings::get_global(cx).clone();
+    WebSearchCache::set_enabled(cx, settings.cache_enabled);
+    WebSearchRateLimiter::configure(cx, settings.requests_per_minute, settings.burst);
+    WebSearchAggregationMode::set_enabled(cx, settings.multi_provider_aggregation_enabled);
+}
+
+/// Maximum number of distinct queries the cache retains at once.
+const WEB_SEARCH_CACHE_CAPACITY: usize = 64;
+/// How long a cached response stays fresh before it's treated as a miss.
+const WEB_SEARCH_CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// An LRU + TTL cache of recent `web_search` results, keyed on the
+/// normalized (query, parameters) pair, so agentic loops that re-issue the
+/// same search don't burn provider quota and latency.
+struct WebSearchCache {
+    enabled: bool,
+    entries: HashMap<String, CachedWebSearchResponse>,
+    order: VecDeque<String>,
+}
+
+#[derive(Clone)]
+struct CachedWebSearchResponse {
+    response: WebSearchResponse,
+    inserted_at: Instant,
+}
+
+impl Global for WebSearchCache {}
+
+impl WebSearchCache {
+    fn global(cx: &mut App) -> &mut Self {
+        cx.default_global::<Self>()
+    }
+
+    fn set_enabled(cx: &mut App, enabled: bool) {
+        Self::global(cx).enabled = enabled;
+    }
+
+    /// Builds a cache key scoped to `provider_key` so switching providers
+    /// (or the set of providers fanned out to) never serves back a result
+    /// another provider produced.
+    fn cache_key(provider_key: &str, query: &str, params: &WebSearchParams) -> String {
+        let mut include_domains = params.include_domains.clone().unwrap_or_default();
+        include_domains.sort();
+        let mut exclude_domains = params.exclude_domains.clone().unwrap_or_default();
+        exclude_domains.sort();
+
+        format!(
+            "{}|{}|{:?}|{}|{}",
+            provider_key,
+            query.trim().to_lowercase(),
+            params.max_results,
+            include_domains.join(","),
+            exclude_domains.join(",")
+        )
+    }
+
+    fn get(&mut self, key: &str) -> Option<WebSearchResponse> {
+        if !self.enabled {
+            return None;
+        }
+
+        let entry = self.entries.get(key)?;
+        if entry.inserted_at.elapsed() > WEB_SEARCH_CACHE_TTL {
+            self.entries.remove(key);
+            self.order.retain(|existing| existing != key);
+            return None;
+        }
+
+        let response = entry.response.clone();
+
+        // Move the hit to the back so eviction is by least-recently-*used*,
+        // not least-recently-*inserted* — a query that's re-hit on every
+        // call should never be evicted just because 64 other queries were
+        // inserted after it.
+        self.order.retain(|existing| existing != key);
+        self.order.push_back(key.to_string());
+
+        Some(response)
+    }
+
+    fn insert(&mut self, key: String, response: WebSearchResponse) {
+        if !self.enabled {
+            return;
+        }
+
+        let entry = CachedWebSearchResponse {
+            response,
+            inserted_at: Instant::now(),
+        };
+        if self.entries.insert(key.clone(), entry).is_none() {
+            self.order.push_back(key);
+        }
+
+        while self.order.len() > WEB_SEARCH_CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl Default for WebSearchCache {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            entries: HashMap::default(),
+            order: VecDeque::new(),
+        }
+    }
+}
+
+/// Default number of `web_search` calls allowed per minute before the
+/// token bucket runs dry.
+const WEB_SEARCH_DEFAULT_REQUESTS_PER_MINUTE: f64 = 20.;
+/// Default number of calls that may be made back-to-back before the
+/// steady-state rate kicks in.
+const WEB_SEARCH_DEFAULT_BURST: f64 = 5.;
+/// Floor applied to a user-configured `requests_per_minute`/`burst`.
+/// `requests_per_minute` is a divisor (via the refill rate) in the
+/// token-bucket math, so a zero or negative value would produce a
+/// non-finite retry-after duration and panic in `Duration::from_secs_f64`;
+/// a `burst` below one would cap the bucket under a whole token, so every
+/// call would be rate limited.
+const WEB_SEARCH_MIN_REQUESTS_PER_MINUTE: f64 = 1.;
+const WEB_SEARCH_MIN_BURST: f64 = 1.;
+
+/// A token-bucket limiter guarding against a runaway agent firing dozens
+/// of `web_search` calls in a burst and getting throttled (or billed
+/// heavily) by the provider.
+struct WebSearchRateLimiter {
+    requests_per_minute: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Global for WebSearchRateLimiter {}
+
+impl Default for WebSearchRateLimiter {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: WEB_SEARCH_DEFAULT_REQUESTS_PER_MINUTE,
+            burst: WEB_SEARCH_DEFAULT_BURST,
+            tokens: WEB_SEARCH_DEFAULT_BURST,
+            last_refill: Instant::now(),
+        }
+    }
+}
+
+impl WebSearchRateLimiter {
+    fn global(cx: &mut App) -> &mut Self {
+        cx.default_global::<Self>()
+    }
+
+    fn configure(cx: &mut App, requests_per_minute: f64, burst: f64) {
+        let requests_per_minute = requests_per_minute.max(WEB_SEARCH_MIN_REQUESTS_PER_MINUTE);
+        let burst = burst.max(WEB_SEARCH_MIN_BURST);
+        let this = Self::global(cx);
+        this.requests_per_minute = requests_per_minute;
+        this.burst = burst;
+        this.tokens = this.tokens.min(burst);
+    }
+
+    /// The refill rate in tokens/second, floored so a non-positive
+    /// `requests_per_minute` (however it got set) can never produce a
+    /// division by zero or negative rate downstream.
+    fn tokens_per_second(&self) -> f64 {
+        self.requests_per_minute.max(WEB_SEARCH_MIN_REQUESTS_PER_MINUTE) / 60.
+    }
+
+    fn refill(&mut self) {
+        let elapsed = self.last_refill.elapsed().as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.tokens_per_second()).min(self.burst);
+        self.last_refill = Instant::now();
+    }
+
+    /// Consumes a token if one is available, otherwise returns how long the
+    /// caller should wait before the next token is minted.
+    fn try_acquire(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Ok(())
+        } else {
+            let deficit = 1. - self.tokens;
+            Err(Duration::from_secs_f64(deficit / self.tokens_per_second()))
+        }
+    }
+}
+
+/// A `web_search` task shared across every caller currently awaiting it,
+/// keyed by [`WebSearchCache::cache_key`] so concurrent tool calls with the
+/// same input coalesce onto a single provider request.
+type SharedSearchTask = Shared<BoxFuture<'static, Result<WebSearchResponse, Arc<anyhow::Error>>>>;
+
+#[derive(Default)]
+struct WebSearchInFlight {
+    tasks: HashMap<String, SharedSearchTask>,
+}
+
+impl Global for WebSearchInFlight {}
+
+impl WebSearchInFlight {
+    fn global(cx: &mut App) -> &mut Self {
+        cx.default_global::<Self>()
+    }
+}
+
+/// `k` constant from the standard Reciprocal Rank Fusion formula,
+/// `score = sum(1 / (k + rank))` over every provider that returned a URL.
+const RECIPROCAL_RANK_FUSION_K: f64 = 60.;
+
+/// Whether `web_search` fans a query out to every registered provider and
+/// merges the results, instead of just querying the active provider.
+#[derive(Default)]
+struct WebSearchAggregationMode {
+    enabled: bool,
+}
+
+impl Global for WebSearchAggregationMode {}
+
+impl WebSearchAggregationMode {
+    fn global(cx: &mut App) -> &mut Self {
+        cx.default_global::<Self>()
+    }
+
+    fn set_enabled(cx: &mut App, enabled: bool) {
+        Self::global(cx).enabled = enabled;
+    }
+}
+
+/// Normalizes a citation URL so the same page returned by different
+/// providers (with different tracking params, query-param order, trailing
+/// slashes, or casing) dedupes to the same key.
+fn normalize_citation_url(url: &str) -> String {
+    let Ok(mut parsed) = url.parse::<url::Url>() else {
+        return url.to_string();
+    };
+
+    parsed.set_fragment(None);
+
+    if let Some(host) = parsed.host_str() {
+        let host = host.to_lowercase();
+        parsed.set_host(Some(&host)).ok();
+    }
+
+    let mut retained_pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    retained_pairs.sort();
+    if retained_pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        let query = retained_pairs
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        parsed.set_query(Some(&query));
+    }
+
+    let path = parsed.path().trim_end_matches('/').to_string();
+    parsed.set_path(&path);
+
+    parsed.to_string()
+}
+
+/// Merges citations from multiple providers' responses into one list using
+/// Reciprocal Rank Fusion, deduping by normalized URL and keeping the
+/// title/snippet from whichever provider returned the URL first.
+fn merge_with_reciprocal_rank_fusion(
+    responses: Vec<WebSearchResponse>,
+) -> Vec<zed_llm_client::WebSearchCitation> {
+    struct Merged {
+        citation: zed_llm_client::WebSearchCitation,
+        score: f64,
+        provider_count: usize,
+    }
+
+    let mut merged: HashMap<String, Merged> = HashMap::new();
+
+    for response in responses {
+        for (rank, citation) in response.citations.into_iter().enumerate() {
+            let key = normalize_citation_url(&citation.url);
+            let score = 1. / (RECIPROCAL_RANK_FUSION_K + rank as f64);
+
+            merged
+                .entry(key)
+                .and_modify(|entry| {
+                    entry.score += score;
+                    entry.provider_count += 1;
+                })
+                .or_insert(Merged {
+                    citation,
+                    score,
+                    provider_count: 1,
+                });
+        }
+    }
+
+    let mut merged: Vec<Merged> = merged.into_values().collect();
+    merged.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.provider_count.cmp(&a.provider_count))
+    });
+
+    merged.into_iter().map(|entry| entry.citation).collect()
+}
+
+/// Target length, in characters, of a cropped citation snippet.
+const SNIPPET_CROP_LEN: usize = 200;
+
+/// A run of snippet text, highlighted if it matched one of the query's
+/// tokens.
+struct SnippetSegment {
+    text: String,
+    matched: bool,
+}
+
+/// Splits `query` on whitespace/punctuation into lowercase tokens.
+fn query_tokens(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+/// Crops `snippet` to a window of [`SNIPPET_CROP_LEN`] characters that
+/// maximizes the number of query-term matches it contains, and splits that
+/// window into highlighted/plain segments. Adds a leading/trailing ellipsis
+/// when the window doesn't cover the whole snippet.
+fn crop_and_highlight_snippet(snippet: &str, query: &str) -> Vec<SnippetSegment> {
+    let chars: Vec<char> = snippet.chars().collect();
+    let lower: Vec<char> = snippet.to_lowercase().chars().collect();
+    let tokens: Vec<Vec<char>> = query_tokens(query)
+        .into_iter()
+        .map(|token| token.chars().collect())
+        .collect();
+
+    let mut matches: Vec<(usize, usize)> = Vec::new();
+    for token in &tokens {
+        if token.is_empty() || token.len() > lower.len() {
+            continue;
+        }
+        let mut start = 0;
+        while start + token.len() <= lower.len() {
+            if lower[start..start + token.len()] == token[..] {
+                matches.push((start, start + token.len()));
+                start += token.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+    matches.sort_unstable_by_key(|&(start, _)| start);
+
+    let crop_len = SNIPPET_CROP_LEN.min(chars.len());
+    let window_start = best_window_start(chars.len(), &matches, crop_len);
+    let window_end = (window_start + crop_len).min(chars.len());
+
+    let mut segments = Vec::new();
+    let mut cursor = window_start;
+    for &(start, end) in matches
+        .iter()
+        .filter(|&&(start, end)| start < window_end && end > window_start)
+    {
+        let start = start.max(window_start).max(cursor);
+        let end = end.min(window_end);
+        if start > cursor {
+            segments.push(SnippetSegment {
+                text: chars[cursor..start].iter().collect(),
+                matched: false,
+            });
+        }
+        if end > start {
+            segments.push(SnippetSegment {
+                text: chars[start..end].iter().collect(),
+                matched: true,
+            });
+            cursor = end;
+        }
+    }
+    if cursor < window_end {
+        segments.push(SnippetSegment {
+            text: chars[cursor..window_end].iter().collect(),
+            matched: false,
+        });
+    }
+
+    if window_start > 0 {
+        segments.insert(
+            0,
+            SnippetSegment {
+                text: "…".into(),
+                matched: false,
+            },
+        );
+    }
+    if window_end < chars.len() {
+        segments.push(SnippetSegment {
+            text: "…".into(),
+            matched: false,
+        });
+    }
+
+    segments
+}
+
+/// Picks the crop window (of length `crop_len`, in chars) that contains the
+/// most query-term matches. Candidate windows are centered on, or start at,
+/// each match; on a tie the candidate for the earliest match wins, centered
+/// before start-aligned.
+fn best_window_start(snippet_len: usize, matches: &[(usize, usize)], crop_len: usize) -> usize {
+    if matches.is_empty() {
+        return 0;
+    }
+
+    let mut best_start = 0;
+    let mut best_count = 0;
+    for &(match_start, _) in matches {
+        for candidate in [match_start.saturating_sub(crop_len / 2), match_start] {
+            let start = candidate.min(snippet_len.saturating_sub(crop_len));
+            let end = (start + crop_len).min(snippet_len);
+            let count = matches
+                .iter()
+                .filter(|&&(s, e)| s >= start && e <= end)
+                .count();
+            if count > best_count {
+                best_count = count;
+                best_start = start;
+            }
+        }
+    }
+
+    best_start
+}
+
+/// Approximate characters per token, used to size the fetched-page window
+/// without invoking a tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
+/// Maximum number of tokens returned by a single `fetch_url` call; longer
+/// pages are paged through via the `offset` input.
+const FETCH_URL_TOKEN_BUDGET: usize = 2000;
+
+/// The readable text extracted from a fetched page, windowed to
+/// [`FETCH_URL_TOKEN_BUDGET`] starting at some `offset`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FetchedPage {
+    url: String,
+    title: Option<String>,
+    text: String,
+    offset: usize,
+    /// Present when the extracted text continues past this window; pass
+    /// this back as the next call's `offset` to keep reading.
+    next_offset: Option<usize>,
+    total_len: usize,
+}
+
+/// Downloads `url` and returns a [`FETCH_URL_TOKEN_BUDGET`]-sized window of
+/// its readability-extracted text, starting at `offset` characters in.
+async fn fetch_readable_page(
+    http_client: Arc<HttpClientWithUrl>,
+    url: &str,
+    offset: usize,
+) -> Result<FetchedPage> {
+    let mut response = http_client
+        .get(url, AsyncBody::default(), true)
+        .await
+        .with_context(|| format!("Failed to fetch {url}"))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to fetch {url}: {}", response.status()));
+    }
+
+    let mut html = String::new();
+    response
+        .body_mut()
+        .read_to_string(&mut html)
+        .await
+        .context("Failed to read response body")?;
+
+    let (title, text) = extract_readable_text(&html);
+    let total_len = text.chars().count();
+    let offset = offset.min(total_len);
+    let window_len = FETCH_URL_TOKEN_BUDGET * CHARS_PER_TOKEN;
+    let window_end = (offset + window_len).min(total_len);
+    let page_text: String = text.chars().skip(offset).take(window_end - offset).collect();
+    let next_offset = (window_end < total_len).then_some(window_end);
+
+    Ok(FetchedPage {
+        url: url.to_string(),
+        title,
+        text: page_text,
+        offset,
+        next_offset,
+        total_len,
+    })
+}
+
+/// Boilerplate elements whose contents (not just markup) should be dropped
+/// before converting the page to plain text.
+const BOILERPLATE_TAGS: &[&str] = &[
+    "script", "style", "nav", "header", "footer", "aside", "form", "noscript",
+];
+
+/// Very small readability-style extractor: drops boilerplate elements and
+/// their contents, strips the remaining tags, and collapses whitespace so
+/// only the page's main textual content survives.
+fn extract_readable_text(html: &str) -> (Option<String>, String) {
+    let title = extract_tag_text(html, "title");
+
+    let mut body = html.to_string();
+    for tag in BOILERPLATE_TAGS {
+        body = strip_tag_blocks(&body, tag);
+    }
+
+    (title, collapse_whitespace(&strip_tags(&body)))
+}
+
+/// Returns the text content of the first `<tag>...</tag>` block, if any.
+fn extract_tag_text(html: &str, tag: &str) -> Option<String> {
+    let lower = html.to_lowercase();
+    let open_start = lower.find(&format!("<{tag}"))?;
+    let content_start = lower[open_start..].find('>')? + open_start + 1;
+    let content_end = lower[content_start..].find(&format!("</{tag}>"))? + content_start;
+
+    let text = collapse_whitespace(&strip_tags(&html[content_start..content_end]));
+    (!text.is_empty()).then_some(text)
+}
+
+/// Removes every (case-insensitive) `<tag ...>...</tag>` block, including
+/// any markup nested inside it.
+fn strip_tag_blocks(html: &str, tag: &str) -> String {
+    let open_prefix = format!("<{tag}");
+    let close_tag = format!("</{tag}>");
+    let lower = html.to_lowercase();
+
+    let mut result = String::with_capacity(html.len());
+    let mut cursor = 0;
+    while let Some(start) = find_tag_open(&lower, cursor, &open_prefix) {
+        result.push_str(&html[cursor..start]);
+
+        let Some(relative_close) = lower[start..].find(&close_tag) else {
+            cursor = html.len();
+            break;
+        };
+        cursor = start + relative_close + close_tag.len();
+    }
+    result.push_str(&html[cursor..]);
+    result
+}
+
+/// Finds the next occurrence of `open_prefix` (e.g. `<nav`) in `lower` at or
+/// after `from` that's an actual tag boundary — the prefix is immediately
+/// followed by whitespace, `>`, `/`, or end of string — rather than a
+/// hyphenated custom element name like `<nav-menu>` that merely starts with
+/// the same characters. A bare substring match there would misidentify the
+/// element's start and, with no matching `</nav>` ever appearing, delete
+/// everything to the end of the document.
+fn find_tag_open(lower: &str, from: usize, open_prefix: &str) -> Option<usize> {
+    let mut search_from = from;
+    loop {
+        let relative = lower[search_from..].find(open_prefix)?;
+        let start = search_from + relative;
+        let after = &lower[start + open_prefix.len()..];
+        let is_boundary = after
+            .chars()
+            .next()
+            .map_or(true, |ch| ch.is_whitespace() || ch == '>' || ch == '/');
+        if is_boundary {
+            return Some(start);
+        }
+        search_from = start + 1;
+    }
+}
+
+/// Strips all remaining `<...>` tags, leaving only their text content.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Collapses whitespace runs (including the newlines left behind by
+/// block-level tags) into single spaces, and decodes the handful of HTML
+/// entities that commonly survive tag-stripping.
+fn collapse_whitespace(text: &str) -> String {
+    let decoded = text
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'");
+
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 pub struct WebSearchTool;
 
+impl WebSearchTool {
+    /// Fans the query out to every registered provider concurrently and
+    /// merges the citations with Reciprocal Rank Fusion. Individual
+    /// provider failures are tolerated (skipped) rather than failing the
+    /// whole search.
+    fn run_aggregated(
+        input: WebSearchToolInput,
+        params: WebSearchParams,
+        project: Entity<Project>,
+        cx: &mut App,
+    ) -> ToolResult {
+        let providers = WebSearchRegistry::read_global(cx).providers();
+        if providers.is_empty() {
+            return Task::ready(Err(anyhow!("Web search is not available."))).into();
+        }
+
+        let provider_key = format!("agg:{}", providers_cache_key(&providers));
+        let cache_key = WebSearchCache::cache_key(&provider_key, &input.query, &params);
+        let engines_searched = providers.len();
+        let query = input.query.clone();
+
+        if let Some(response) = WebSearchCache::global(cx).get(&cache_key) {
+            let search_task = Task::ready(Ok(response)).shared();
+            let output = cx.background_spawn({
+                let search_task = search_task.clone();
+                async move {
+                    let response = search_task.await.map_err(|err| anyhow!(err))?;
+                    serde_json::to_string(&response).context("Failed to serialize search results")
+                }
+            });
+
+            return ToolResult {
+                output,
+                card: Some(
+                    cx.new(|cx| {
+                        WebSearchToolCard::new(
+                            search_task,
+                            query,
+                            true,
+                            Some(engines_searched),
+                            project,
+                            cx,
+                        )
+                    })
+                    .into(),
+                ),
+            };
+        }
+
+        if let Some(search_task) = WebSearchInFlight::global(cx).tasks.get(&cache_key).cloned() {
+            let output = cx.background_spawn({
+                let search_task = search_task.clone();
+                async move {
+                    let response = search_task.await.map_err(|err| anyhow!(err))?;
+                    serde_json::to_string(&response).context("Failed to serialize search results")
+                }
+            });
+
+            return ToolResult {
+                output,
+                card: Some(
+                    cx.new(|cx| {
+                        WebSearchToolCard::new(
+                            search_task,
+                            query,
+                            false,
+                            Some(engines_searched),
+                            project,
+                            cx,
+                        )
+                    })
+                    .into(),
+                ),
+            };
+        }
+
+        if let Err(retry_after) = WebSearchRateLimiter::global(cx).try_acquire() {
+            return Task::ready(Err(anyhow!(
+                "Rate limited, try again in {}s",
+                retry_after.as_secs().max(1)
+            )))
+            .into();
+        }
+
+        let provider_searches: Vec<_> = providers
+            .iter()
+            .map(|provider| provider.search(input.query.clone(), cx))
+            .collect();
+
+        let search_task: SharedSearchTask = cx
+            .background_spawn({
+                let params = params.clone();
+                async move {
+                    let results = join_all(provider_searches).await;
+                    let successful: Vec<WebSearchResponse> =
+                        results.into_iter().filter_map(Result::ok).collect();
+                    if successful.is_empty() {
+                        return Err(Arc::new(anyhow!("All search providers failed.")));
+                    }
+                    let template = successful[0].clone();
+                    let citations = merge_with_reciprocal_rank_fusion(successful);
+                    Ok(params.post_filter(WebSearchResponse {
+                        citations,
+                        ..template
+                    }))
+                }
+            })
+            .boxed()
+            .shared();
+
+        WebSearchInFlight::global(cx)
+            .tasks
+            .insert(cache_key.clone(), search_task.clone());
+        cx.spawn({
+            let search_task = search_task.clone();
+            let cache_key = cache_key.clone();
+            async move |cx| {
+                let response = search_task.await;
+                cx.update(|cx| {
+                    WebSearchInFlight::global(cx).tasks.remove(&cache_key);
+                    if let Ok(response) = response {
+                        WebSearchCache::global(cx).insert(cache_key, response);
+                    }
+                })
+                .ok();
+            }
+        })
+        .detach();
+
+        let output = cx.background_spawn({
+            let search_task = search_task.clone();
+            async move {
+                let response = search_task.await.map_err(|err| anyhow!(err))?;
+                serde_json::to_string(&response).context("Failed to serialize search results")
+            }
+        });
+
+        ToolResult {
+            output,
+            card: Some(
+                cx.new(|cx| {
+                    WebSearchToolCard::new(
+                        search_task,
+                        query,
+                        false,
+                        Some(engines_searched),
+                        project,
+                        cx,
+                    )
+                })
+                .into(),
+            ),
+        }
+    }
+}
+
 impl Tool for WebSearchTool {
     fn name(&self) -> String {
         "web_search".into()
@@ -53,7 +1005,7 @@ impl Tool for WebSearchTool {
         self: Arc<Self>,
         input: serde_json::Value,
         _messages: &[LanguageModelRequestMessage],
-        _project: Entity<Project>,
+        project: Entity<Project>,
         _action_log: Entity<ActionLog>,
         cx: &mut App,
     ) -> ToolResult {
@@ -61,11 +1013,96 @@ impl Tool for WebSearchTool {
             Ok(input) => input,
             Err(err) => return Task::ready(Err(anyhow!(err))).into(),
         };
+
+        let params = WebSearchParams::from_input(&input);
+
+        if WebSearchAggregationMode::global(cx).enabled {
+            return Self::run_aggregated(input, params, project, cx);
+        }
+
         let Some(provider) = WebSearchRegistry::read_global(cx).active_provider() else {
             return Task::ready(Err(anyhow!("Web search is not available."))).into();
         };
 
-        let search_task = provider.search(input.query, cx).map_err(Arc::new).shared();
+        let provider_key = provider_cache_key(&provider);
+        let cache_key = WebSearchCache::cache_key(&provider_key, &input.query, &params);
+        let query = input.query.clone();
+
+        if let Some(response) = WebSearchCache::global(cx).get(&cache_key) {
+            let search_task = Task::ready(Ok(response)).shared();
+            let output = cx.background_spawn({
+                let search_task = search_task.clone();
+                async move {
+                    let response = search_task.await.map_err(|err| anyhow!(err))?;
+                    serde_json::to_string(&response).context("Failed to serialize search results")
+                }
+            });
+
+            return ToolResult {
+                output,
+                card: Some(
+                    cx.new(|cx| {
+                        WebSearchToolCard::new(search_task, query, true, None, project, cx)
+                    })
+                    .into(),
+                ),
+            };
+        }
+
+        if let Some(search_task) = WebSearchInFlight::global(cx).tasks.get(&cache_key).cloned() {
+            let output = cx.background_spawn({
+                let search_task = search_task.clone();
+                async move {
+                    let response = search_task.await.map_err(|err| anyhow!(err))?;
+                    serde_json::to_string(&response).context("Failed to serialize search results")
+                }
+            });
+
+            return ToolResult {
+                output,
+                card: Some(
+                    cx.new(|cx| {
+                        WebSearchToolCard::new(search_task, query, false, None, project, cx)
+                    })
+                    .into(),
+                ),
+            };
+        }
+
+        if let Err(retry_after) = WebSearchRateLimiter::global(cx).try_acquire() {
+            return Task::ready(Err(anyhow!(
+                "Rate limited, try again in {}s",
+                retry_after.as_secs().max(1)
+            )))
+            .into();
+        }
+
+        let search_task: SharedSearchTask = provider
+            .search(input.query, cx)
+            .map_err(Arc::new)
+            .map_ok({
+                let params = params.clone();
+                move |response| params.post_filter(response)
+            })
+            .boxed()
+            .shared();
+        WebSearchInFlight::global(cx)
+            .tasks
+            .insert(cache_key.clone(), search_task.clone());
+        cx.spawn({
+            let search_task = search_task.clone();
+            async move |cx| {
+                let response = search_task.await;
+                cx.update(|cx| {
+                    WebSearchInFlight::global(cx).tasks.remove(&cache_key);
+                    if let Ok(response) = response {
+                        WebSearchCache::global(cx).insert(cache_key, response);
+                    }
+                })
+                .ok();
+            }
+        })
+        .detach();
         let output = cx.background_spawn({
             let search_task = search_task.clone();
             async move {
@@ -76,19 +1113,114 @@ impl Tool for WebSearchTool {
 
         ToolResult {
             output,
-            card: Some(cx.new(|cx| WebSearchToolCard::new(search_task, cx)).into()),
+            card: Some(
+                cx.new(|cx| WebSearchToolCard::new(search_task, query, false, None, project, cx))
+                    .into(),
+            ),
         }
     }
 }
 
+/// A `fetch_url` input, whose companion tool downloads a web page and
+/// returns its readability-extracted text. Lives alongside [`WebSearchTool`]
+/// since it's the natural follow-up to a `web_search` citation.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FetchUrlToolInput {
+    /// The URL of the page to fetch, typically one returned as a citation
+    /// by `web_search`.
+    url: String,
+    /// Character offset into the extracted article text to resume from.
+    /// Pass the previous response's `next_offset` to keep reading a long
+    /// page.
+    #[serde(default)]
+    offset: usize,
+}
+
+pub struct FetchUrlTool;
+
+impl Tool for FetchUrlTool {
+    fn name(&self) -> String {
+        "fetch_url".into()
+    }
+
+    fn needs_confirmation(&self, _: &serde_json::Value, _: &App) -> bool {
+        false
+    }
+
+    fn description(&self) -> String {
+        "Fetch a web page and extract its main readable text, with navigation, ads, and other boilerplate stripped out. Use this to read a `web_search` citation in full. Long pages are paginated: pass the previous response's `next_offset` as `offset` to keep reading.".into()
+    }
+
+    fn icon(&self) -> IconName {
+        IconName::Globe
+    }
+
+    fn input_schema(&self, format: LanguageModelToolSchemaFormat) -> Result<serde_json::Value> {
+        json_schema_for::<FetchUrlToolInput>(format)
+    }
+
+    fn ui_text(&self, input: &serde_json::Value) -> String {
+        match serde_json::from_value::<FetchUrlToolInput>(input.clone()) {
+            Ok(input) => format!("Read {}", input.url),
+            Err(_) => "Read Page".to_string(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        _messages: &[LanguageModelRequestMessage],
+        project: Entity<Project>,
+        _action_log: Entity<ActionLog>,
+        cx: &mut App,
+    ) -> ToolResult {
+        let input = match serde_json::from_value::<FetchUrlToolInput>(input) {
+            Ok(input) => input,
+            Err(err) => return Task::ready(Err(anyhow!(err))).into(),
+        };
+
+        let http_client = project.read(cx).client().http_client();
+        let output = cx.background_spawn(async move {
+            let page = fetch_readable_page(http_client, &input.url, input.offset).await?;
+            serde_json::to_string(&page).context("Failed to serialize fetched page")
+        });
+
+        ToolResult { output, card: None }
+    }
+}
+
+/// Result of an in-place "Read" expansion of a citation into its fetched,
+/// readability-extracted article text. `None` while the fetch is still in
+/// flight.
+enum ReadCitationResult {
+    Loaded(FetchedPage),
+    Error(String),
+}
+
 struct WebSearchToolCard {
     response: Option<Result<WebSearchResponse>>,
+    query: String,
+    cached: bool,
+    /// Set when the query was fanned out to multiple providers, recording
+    /// how many engines were searched.
+    engines_searched: Option<usize>,
+    project: Entity<Project>,
+    /// Keyed by citation index; populated by the citation row's "Read"
+    /// button so the user can expand a result into its full article text
+    /// without leaving the tool card. `None` while the fetch is in flight.
+    read_citations: HashMap<usize, Option<ReadCitationResult>>,
+    /// Keeps each in-flight "Read" fetch alive until it resolves.
+    _read_tasks: HashMap<usize, Task<()>>,
     _task: Task<()>,
 }
 
 impl WebSearchToolCard {
     fn new(
         search_task: impl 'static + Future<Output = Result<WebSearchResponse, Arc<anyhow::Error>>>,
+        query: String,
+        cached: bool,
+        engines_searched: Option<usize>,
+        project: Entity<Project>,
         cx: &mut Context<Self>,
     ) -> Self {
         let _task = cx.spawn(async move |this, cx| {
@@ -102,9 +1234,45 @@ impl WebSearchToolCard {
 
         Self {
             response: None,
+            query,
+            cached,
+            engines_searched,
+            project,
+            read_citations: HashMap::default(),
+            _read_tasks: HashMap::default(),
             _task,
         }
     }
+
+    /// Fetches (or, if already expanded, collapses) the full article text
+    /// for the citation at `index`.
+    fn toggle_read_citation(&mut self, index: usize, url: SharedString, cx: &mut Context<Self>) {
+        if self.read_citations.remove(&index).is_some() {
+            self._read_tasks.remove(&index);
+            cx.notify();
+            return;
+        }
+
+        let http_client = self.project.read(cx).client().http_client();
+        let task = cx.spawn(async move |this, cx| {
+            let result = fetch_readable_page(http_client, &url, 0).await;
+            this.update(cx, |this, cx| {
+                this.read_citations.insert(
+                    index,
+                    Some(match result {
+                        Ok(page) => ReadCitationResult::Loaded(page),
+                        Err(err) => ReadCitationResult::Error(err.to_string()),
+                    }),
+                );
+                cx.notify();
+            })
+            .ok();
+        });
+
+        self.read_citations.insert(index, None);
+        self._read_tasks.insert(index, task);
+        cx.notify();
+    }
 }
 
 impl ToolCard for WebSearchToolCard {
@@ -131,9 +1299,17 @@ impl ToolCard for WebSearchToolCard {
                     } else {
                         format!("{} results", response.citations.len()).into()
                     };
+                    let label: SharedString = match self.engines_searched {
+                        Some(engines) => format!(
+                            "Searched {engines} engine{}",
+                            if engines == 1 { "" } else { "s" }
+                        )
+                        .into(),
+                        None => "Searched the Web".into(),
+                    };
                     h_flex()
                         .gap_1p5()
-                        .child(Label::new("Searched the Web").size(LabelSize::Small))
+                        .child(Label::new(label).size(LabelSize::Small))
                         .child(
                             div()
                                 .size(px(3.))
@@ -141,6 +1317,19 @@ impl ToolCard for WebSearchToolCard {
                                 .bg(cx.theme().colors().text),
                         )
                         .child(Label::new(text).size(LabelSize::Small))
+                        .when(self.cached, |this| {
+                            this.child(
+                                div()
+                                    .px_1()
+                                    .rounded_sm()
+                                    .bg(cx.theme().colors().element_background)
+                                    .child(
+                                        Label::new("cached")
+                                            .size(LabelSize::XSmall)
+                                            .color(Color::Muted),
+                                    ),
+                            )
+                        })
                         .into_any_element()
                 }
                 Some(Err(error)) => div()
@@ -177,7 +1366,7 @@ impl ToolCard for WebSearchToolCard {
                                     let title = citation.title.clone();
                                     let url = citation.url.clone();
 
-                                    Button::new(("citation", index), title)
+                                    let link = Button::new(("citation", index), title)
                                         .label_size(LabelSize::Small)
                                         .color(Color::Muted)
                                         .icon(IconName::ArrowUpRight)
@@ -199,7 +1388,75 @@ impl ToolCard for WebSearchToolCard {
                                         .on_click({
                                             let url = url.clone();
                                             move |_, _, cx| cx.open_url(&url)
-                                        })
+                                        });
+
+                                    let snippet = (!citation.snippet.is_empty()).then(|| {
+                                        div().pl_5().child(h_flex().flex_wrap().children(
+                                            crop_and_highlight_snippet(
+                                                &citation.snippet,
+                                                &self.query,
+                                            )
+                                            .into_iter()
+                                            .map(|segment| {
+                                                Label::new(segment.text)
+                                                    .size(LabelSize::Small)
+                                                    .color(if segment.matched {
+                                                        Color::Default
+                                                    } else {
+                                                        Color::Muted
+                                                    })
+                                            }),
+                                        ))
+                                    });
+
+                                    let read_state = self.read_citations.get(&index);
+                                    let read_button = Button::new(("read-citation", index), "Read")
+                                        .label_size(LabelSize::Small)
+                                        .color(Color::Muted)
+                                        .icon(IconName::ChevronDown)
+                                        .icon_size(IconSize::XSmall)
+                                        .icon_position(IconPosition::End)
+                                        .selected(read_state.is_some())
+                                        .on_click(cx.listener({
+                                            let url = url.clone();
+                                            move |this, _, _, cx| {
+                                                this.toggle_read_citation(
+                                                    index,
+                                                    url.clone().into(),
+                                                    cx,
+                                                )
+                                            }
+                                        }));
+
+                                    let article = read_state.map(|result| match result {
+                                        None => div().pl_5().child(
+                                            Label::new("Fetching page…")
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted),
+                                        ),
+                                        Some(ReadCitationResult::Loaded(page)) => {
+                                            div().pl_5().child(
+                                                Label::new(page.text.clone())
+                                                    .size(LabelSize::Small)
+                                                    .color(Color::Default),
+                                            )
+                                        }
+                                        Some(ReadCitationResult::Error(error)) => {
+                                            div().pl_5().child(
+                                                Label::new(format!(
+                                                    "Couldn't fetch page: {error}"
+                                                ))
+                                                .size(LabelSize::Small)
+                                                .color(Color::Error),
+                                            )
+                                        }
+                                    });
+
+                                    v_flex()
+                                        .gap_0p5()
+                                        .child(h_flex().gap_1().child(link).child(read_button))
+                                        .children(snippet)
+                                        .children(article)
                                 },
                             ))
                             .into_any(),
@@ -211,3 +1468,225 @@ impl ToolCard for WebSearchToolCard {
         v_flex().my_2().gap_1().child(header).children(content)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_tokens_splits_on_punctuation_and_lowercases() {
+        assert_eq!(
+            query_tokens("Rust's \"async\" runtime?"),
+            vec!["rust", "s", "async", "runtime"]
+        );
+        assert_eq!(query_tokens("   "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn normalize_citation_url_dedupes_tracking_params_and_casing() {
+        assert_eq!(
+            normalize_citation_url("https://Example.com/Path/?utm_source=x&b=2&a=1#frag"),
+            normalize_citation_url("https://example.com/Path?a=1&b=2")
+        );
+        assert_eq!(
+            normalize_citation_url("https://example.com/path/"),
+            "https://example.com/path"
+        );
+    }
+
+    #[test]
+    fn url_host_lowercases_and_ignores_path() {
+        assert_eq!(
+            url_host("https://Example.COM/some/path?x=1"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(url_host("not a url"), None);
+    }
+
+    #[test]
+    fn crop_and_highlight_snippet_highlights_query_terms() {
+        let segments = crop_and_highlight_snippet("The quick brown fox jumps", "quick fox");
+        let highlighted: Vec<&str> = segments
+            .iter()
+            .filter(|segment| segment.matched)
+            .map(|segment| segment.text.as_str())
+            .collect();
+        assert_eq!(highlighted, vec!["quick", "fox"]);
+    }
+
+    #[test]
+    fn crop_and_highlight_snippet_does_not_duplicate_overlapping_matches() {
+        // "rust" is a prefix of "rustacean", so their match ranges overlap.
+        let segments = crop_and_highlight_snippet("a rustacean loves rust", "rust rustacean");
+        let rebuilt: String = segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(rebuilt, "a rustacean loves rust");
+    }
+
+    #[test]
+    fn best_window_start_prefers_window_with_most_matches() {
+        assert_eq!(best_window_start(100, &[], 20), 0);
+        let matches = vec![(10, 14), (50, 54), (52, 56)];
+        let start = best_window_start(100, &matches, 20);
+        assert!(start <= 52 && start + 20 >= 56);
+    }
+
+    #[test]
+    fn web_search_cache_key_is_order_independent_and_provider_scoped() {
+        let mut params = WebSearchParams {
+            max_results: Some(5),
+            include_domains: Some(vec!["b.com".into(), "a.com".into()]),
+            exclude_domains: None,
+        };
+        let key_a = WebSearchCache::cache_key("provider-1", "  Rust Async  ", &params);
+        params.include_domains = Some(vec!["a.com".into(), "b.com".into()]);
+        let key_b = WebSearchCache::cache_key("provider-1", "rust async", &params);
+        assert_eq!(key_a, key_b);
+
+        let key_other_provider = WebSearchCache::cache_key("provider-2", "rust async", &params);
+        assert_ne!(key_a, key_other_provider);
+    }
+
+    fn sample_response(url: &str) -> WebSearchResponse {
+        WebSearchResponse {
+            citations: vec![zed_llm_client::WebSearchCitation {
+                title: "Title".into(),
+                url: url.to_string(),
+                snippet: "Snippet".into(),
+            }],
+        }
+    }
+
+    #[test]
+    fn web_search_cache_evicts_least_recently_used_not_least_recently_inserted() {
+        let mut cache = WebSearchCache::default();
+
+        for i in 0..WEB_SEARCH_CACHE_CAPACITY {
+            cache.insert(format!("k{i}"), sample_response("https://example.com"));
+        }
+
+        // Touch "k0" so it's the most, not least, recently used entry.
+        assert!(cache.get("k0").is_some());
+
+        // Inserting one more entry pushes the cache one over capacity,
+        // forcing a single eviction.
+        cache.insert("k-new".into(), sample_response("https://example.com"));
+
+        assert!(
+            cache.get("k0").is_some(),
+            "recently-used entry should survive eviction"
+        );
+        assert!(
+            cache.get("k1").is_none(),
+            "least-recently-used entry should be evicted instead"
+        );
+    }
+
+    #[test]
+    fn web_search_cache_treats_expired_entries_as_misses() {
+        let mut cache = WebSearchCache::default();
+        cache.entries.insert(
+            "stale".into(),
+            CachedWebSearchResponse {
+                response: sample_response("https://example.com"),
+                inserted_at: Instant::now() - WEB_SEARCH_CACHE_TTL - Duration::from_secs(1),
+            },
+        );
+        cache.order.push_back("stale".into());
+
+        assert!(cache.get("stale").is_none());
+        assert!(cache.order.is_empty());
+    }
+
+    #[test]
+    fn web_search_cache_disabled_never_returns_or_stores_entries() {
+        let mut cache = WebSearchCache::default();
+        cache.enabled = false;
+
+        cache.insert("k".into(), sample_response("https://example.com"));
+        assert!(cache.get("k").is_none());
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn web_search_rate_limiter_depletes_and_reports_retry_after() {
+        let mut limiter = WebSearchRateLimiter {
+            requests_per_minute: 60.,
+            burst: 2.,
+            tokens: 2.,
+            last_refill: Instant::now(),
+        };
+
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_err());
+    }
+
+    #[test]
+    fn web_search_rate_limiter_does_not_panic_on_non_positive_requests_per_minute() {
+        let mut limiter = WebSearchRateLimiter {
+            requests_per_minute: 0.,
+            burst: 1.,
+            tokens: 0.,
+            last_refill: Instant::now(),
+        };
+        // Would divide by zero (and panic in `Duration::from_secs_f64` on the
+        // resulting non-finite value) without the floor in `tokens_per_second`.
+        // Merely returning here, rather than panicking, is the assertion.
+        let retry_after = limiter.try_acquire().expect_err("bucket starts empty");
+        assert!(retry_after > Duration::ZERO);
+
+        let mut limiter = WebSearchRateLimiter {
+            requests_per_minute: -5.,
+            burst: 1.,
+            tokens: 0.,
+            last_refill: Instant::now(),
+        };
+        let retry_after = limiter.try_acquire().expect_err("bucket starts empty");
+        assert!(retry_after > Duration::ZERO);
+    }
+
+    #[test]
+    fn web_search_rate_limiter_configure_clamps_existing_tokens_to_new_burst() {
+        let mut limiter = WebSearchRateLimiter {
+            requests_per_minute: 60.,
+            burst: 5.,
+            tokens: 5.,
+            last_refill: Instant::now(),
+        };
+        limiter.burst = 1.;
+        limiter.tokens = limiter.tokens.min(limiter.burst);
+        assert_eq!(limiter.tokens, 1.);
+    }
+
+    #[test]
+    fn extract_readable_text_drops_boilerplate_and_keeps_body_text() {
+        let html = "<html>\n<head>\n<title>My Page</title>\n<style>.a{color:red}</style>\n</head>\n\
+                     <body>\n<nav>Home</nav>\n<p>Hello &amp; welcome.</p>\n<script>alert(1)</script>\n</body>\n</html>";
+        let (title, text) = extract_readable_text(html);
+        assert_eq!(title.as_deref(), Some("My Page"));
+        assert_eq!(text, "My Page Hello & welcome.");
+    }
+
+    #[test]
+    fn strip_tag_blocks_removes_nested_markup() {
+        let html = "<p>keep</p><script>var x = \"<p>not kept</p>\";</script><p>also keep</p>";
+        let stripped = strip_tag_blocks(html, "script");
+        assert_eq!(stripped, "<p>keep</p><p>also keep</p>");
+    }
+
+    #[test]
+    fn strip_tag_blocks_does_not_mistake_hyphenated_custom_elements_for_boilerplate() {
+        let html =
+            "<body><header-bar>Site Name</header-bar><main><p>Real article content here.</p></main></body>";
+        let stripped = strip_tag_blocks(html, "header");
+        assert_eq!(stripped, html);
+    }
+
+    #[test]
+    fn collapse_whitespace_decodes_entities_and_collapses_runs() {
+        assert_eq!(
+            collapse_whitespace("  a\n\tb  &nbsp; c &amp; d  "),
+            "a b c & d"
+        );
+    }
+}